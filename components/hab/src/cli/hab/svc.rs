@@ -4,7 +4,8 @@ use super::util::{CacheKeyPath,
                   ConfigOptRemoteSup,
                   PkgIdent,
                   RemoteSup};
-use crate::error::Result;
+use crate::error::{Error,
+                    Result};
 use configopt::{configopt_fields,
                 ConfigOpt};
 use habitat_core::{os::process::ShutdownTimeout,
@@ -13,13 +14,34 @@ use habitat_core::{os::process::ShutdownTimeout,
                              ServiceGroup},
                    ChannelIdent};
 use habitat_sup_protocol::types::UpdateCondition;
-use std::path::{Path,
-                PathBuf};
+use log::{error,
+          warn};
+use notify::{DebouncedEvent,
+             RecursiveMode,
+             Watcher};
+use schemars::{schema::{InstanceType,
+                        Metadata,
+                        ObjectValidation,
+                        RootSchema,
+                        Schema,
+                        SchemaObject},
+               Map};
+use std::{collections::HashMap,
+          path::{Path,
+                 PathBuf},
+          sync::mpsc::channel,
+          time::Duration};
 use structopt::StructOpt;
 use url::Url;
 use walkdir::WalkDir;
 
 const DEFAULT_SVC_CONFIG_PATH: &str = "/hab/sup/default/config/svc";
+/// The default `svc.toml` patched onto every per-service config file; shared between `Load`'s
+/// `default_config_file` attribute and `validate_svc_loads_from_paths` so the two can't drift.
+const DEFAULT_SVC_TOML_PATH: &str = "/hab/sup/default/config/svc.toml";
+/// How long to let a burst of filesystem events (e.g. an editor's save-as-rename-and-write) settle
+/// before recomputing the desired service set.
+const BULKLOAD_WATCH_DEBOUNCE: Duration = Duration::from_millis(2000);
 
 /// Commands relating to Habitat services
 #[derive(ConfigOpt, StructOpt)]
@@ -90,7 +112,31 @@ pub struct BulkLoad {
     /// Paths to files or directories of service config files
     #[structopt(long = "svc-config-paths",
                 default_value = "/hab/sup/default/config/svc")]
-    pub svc_config_paths: Vec<PathBuf>,
+    pub svc_config_paths:       Vec<PathBuf>,
+    /// Keep watching the svc-config-paths trees and reconcile the running services whenever a
+    /// file is added, changed, or removed, instead of scanning once and exiting
+    // `rename_all = "screamingsnake"` above means each flag's real `clap::Arg` name is the
+    // SCREAMING_SNAKE_CASE form of its field, not the field identifier itself, so the
+    // `conflicts_with_all` lists below must reference those names rather than `watch`,
+    // `generate_config_schema`, etc.
+    #[structopt(long = "watch",
+                conflicts_with_all = &["GENERATE_CONFIG_SCHEMA", "EXPLAIN", "VALIDATE"])]
+    pub watch:                  bool,
+    /// Print the JSON Schema for the service config TOML format accepted by this command, then
+    /// exit without loading anything
+    #[structopt(long = "generate-config-schema",
+                conflicts_with_all = &["WATCH", "EXPLAIN", "VALIDATE"])]
+    pub generate_config_schema: bool,
+    /// Print, for each service, which layer (environment variable, per-service config file, or
+    /// default svc.toml) supplied each resolved field, instead of loading anything
+    #[structopt(long = "explain",
+                conflicts_with_all = &["WATCH", "GENERATE_CONFIG_SCHEMA", "VALIDATE"])]
+    pub explain:                bool,
+    /// Walk the svc-config-paths trees and report every invalid file instead of loading
+    /// anything; exits non-zero if any file fails to parse
+    #[structopt(long = "validate",
+                conflicts_with_all = &["WATCH", "GENERATE_CONFIG_SCHEMA", "EXPLAIN"])]
+    pub validate:               bool,
 }
 
 #[derive(ConfigOpt, StructOpt)]
@@ -122,7 +168,7 @@ impl GROUP_DEFAULT {
 
 fn health_check_interval_default() -> u64 { 30 }
 
-#[derive(ConfigOpt, StructOpt, Deserialize, Debug)]
+#[derive(ConfigOpt, StructOpt, Deserialize, Debug, Clone, PartialEq)]
 #[configopt(attrs(serde), derive(Clone, Debug))]
 #[serde(deny_unknown_fields)]
 #[structopt(no_version, rename_all = "screamingsnake")]
@@ -216,37 +262,228 @@ pub struct SharedLoad {
 }
 
 #[configopt_fields]
-#[derive(ConfigOpt, StructOpt, Deserialize, Debug)]
+#[derive(ConfigOpt, StructOpt, Deserialize, Debug, Clone, PartialEq)]
 #[configopt(attrs(serde),
             derive(Clone, Debug),
-            default_config_file("/hab/sup/default/config/svc.toml"))]
+            default_config_file(DEFAULT_SVC_TOML_PATH))]
 #[serde(deny_unknown_fields)]
 #[structopt(name = "load", no_version, rename_all = "screamingsnake")]
 pub struct Load {
     #[structopt(flatten)]
-    pub pkg_ident:   PkgIdent,
+    pub pkg_ident:              PkgIdent,
     /// Load or reload an already loaded service. If the service was previously loaded and
     /// running this operation will also restart the service
     #[structopt(short = "f", long = "force")]
     #[serde(default)]
-    pub force:       bool,
+    pub force:                  bool,
     #[structopt(flatten)]
     #[serde(flatten)]
-    pub remote_sup:  RemoteSup,
+    pub remote_sup:             RemoteSup,
     #[structopt(flatten)]
     #[serde(flatten)]
-    pub shared_load: SharedLoad,
+    pub shared_load:            SharedLoad,
+    /// Print the JSON Schema for the service config TOML format accepted by this command, then
+    /// exit without loading anything
+    #[structopt(long = "generate-config-schema")]
+    #[serde(skip)]
+    pub generate_config_schema: bool,
+    /// Print which layer (command-line flag, environment variable, or default svc.toml) supplied
+    /// each resolved field, instead of loading anything
+    #[structopt(long = "explain")]
+    #[serde(skip)]
+    pub explain:                bool,
+}
+
+/// Render the JSON Schema describing the service config TOML format accepted by `hab svc load`
+/// and `hab svc bulkload --svc-config-paths`.
+///
+/// `SharedLoad`/`Load` embed several foreign-crate field types (`ChannelIdent`, `ServiceBind`,
+/// `Url`, the `habitat_sup_protocol::types` enums, ...) that don't implement
+/// `schemars::JsonSchema`, so rather than deriving the schema directly on those types, it is
+/// hand-built here to mirror the TOML keys those fields are (de)serialized under.
+pub fn svc_load_config_schema() -> Result<String> {
+    let schema = svc_load_root_schema();
+    serde_json::to_string_pretty(&schema).map_err(Error::SerdeJson)
+}
+
+fn string_schema(description: &str) -> Schema { typed_schema(InstanceType::String, description) }
+
+fn integer_schema(description: &str) -> Schema { typed_schema(InstanceType::Integer, description) }
+
+fn typed_schema(instance_type: InstanceType, description: &str) -> Schema {
+    Schema::Object(SchemaObject { instance_type: Some(instance_type.into()),
+                                  metadata: Some(Box::new(Metadata { description:
+                                                                          Some(description.to_owned()),
+                                                                      ..Default::default() })),
+                                  ..Default::default() })
+}
+
+/// Attach the JSON Schema `default` keyword to an already-built field schema, mirroring the
+/// `default_value` given to the corresponding `structopt` flag.
+fn with_default(schema: Schema, default: serde_json::Value) -> Schema {
+    match schema {
+        Schema::Object(mut schema_object) => {
+            schema_object.metadata.get_or_insert_with(Default::default).default = Some(default);
+            Schema::Object(schema_object)
+        }
+        Schema::Bool(_) => unreachable!("field schemas are always built as Schema::Object"),
+    }
+}
+
+fn enum_schema(possible_values: &[&str], description: &str) -> Schema {
+    let mut schema_object = match string_schema(description) {
+        Schema::Object(schema_object) => schema_object,
+        Schema::Bool(_) => unreachable!("string_schema always returns a Schema::Object"),
+    };
+    schema_object.enum_values = Some(possible_values.iter()
+                                                     .map(|v| serde_json::Value::String((*v).to_owned()))
+                                                     .collect());
+    Schema::Object(schema_object)
+}
+
+fn array_of_strings_schema(description: &str) -> Schema {
+    use schemars::schema::{ArrayValidation, SingleOrVec};
+    let item = string_schema("A service group to bind, e.g. backend.default");
+    Schema::Object(SchemaObject { instance_type: Some(InstanceType::Array.into()),
+                                  metadata: Some(Box::new(Metadata { description:
+                                                                          Some(description.to_owned()),
+                                                                      ..Default::default() })),
+                                  array:
+                                      Some(Box::new(ArrayValidation { items:
+                                                                          Some(SingleOrVec::Single(Box::new(item))),
+                                                                      ..Default::default() })),
+                                  ..Default::default() })
+}
+
+/// Hand-built JSON Schema for the service config TOML format, covering the fields that a
+/// per-service config file or `svc.toml` may set: [`OVERLAYABLE_FIELDS`], `config_from` and
+/// `force`, plus the package identifier every config is keyed on. `remote_sup` (and, on Windows,
+/// `password`) are not listed, so a file that sets them does not validate against this schema.
+///
+/// `pkg_ident` is the only field without a default: every other field can be left out of a
+/// per-service file and falls back to the default `svc.toml` value, so only `pkg_ident` is
+/// `required`. Unknown keys are rejected outright (`additional_properties: false`), and the
+/// defaults mirror each flag's `default_value` so this catches a misspelled or missing field
+/// before it ever hits the Supervisor's `deny_unknown_fields` parse.
+fn svc_load_root_schema() -> RootSchema {
+    let mut properties = Map::new();
+    properties.insert("pkg_ident".to_owned(),
+                       string_schema("Package identifier, e.g. core/redis"));
+    properties.insert("channel".to_owned(),
+                       with_default(string_schema("Receive updates from the specified release \
+                                                    channel"),
+                                    serde_json::Value::String(CHANNEL_IDENT_DEFAULT.clone())));
+    properties.insert("bldr_url".to_owned(),
+                       string_schema("An alternate Builder endpoint"));
+    properties.insert("group".to_owned(),
+                       with_default(string_schema("The service group with shared config and \
+                                                    topology"),
+                                    serde_json::Value::String(GROUP_DEFAULT.clone())));
+    properties.insert("topology".to_owned(),
+                       enum_schema(&["standalone", "leader"], "Service topology"));
+    properties.insert("strategy".to_owned(),
+                       with_default(enum_schema(&["none", "at-once", "rolling"],
+                                                 "The update strategy"),
+                                    serde_json::Value::String("none".to_owned())));
+    properties.insert("update_condition".to_owned(),
+                       with_default(enum_schema(UpdateCondition::VARIANTS,
+                                                 "The condition dictating when this service \
+                                                  should update"),
+                                    serde_json::Value::String(UpdateCondition::Latest.as_str()
+                                                                                     .to_owned())));
+    properties.insert("bind".to_owned(),
+                       array_of_strings_schema("One or more service groups to bind to a \
+                                                 configuration"));
+    properties.insert("binding_mode".to_owned(),
+                       with_default(enum_schema(&["strict", "relaxed"],
+                                                 "Governs how the presence or absence of binds \
+                                                  affects service startup"),
+                                    serde_json::Value::String("strict".to_owned())));
+    properties.insert("health_check_interval".to_owned(),
+                       with_default(integer_schema("The interval in seconds on which to run \
+                                                     health checks"),
+                                    serde_json::Value::from(30)));
+    properties.insert("shutdown_timeout".to_owned(),
+                       integer_schema("The delay in seconds after sending the shutdown signal \
+                                        to wait before killing the service process"));
+    properties.insert("config_from".to_owned(),
+                       string_schema("Use the package config from this path rather than the \
+                                       package itself"));
+    properties.insert("force".to_owned(),
+                       typed_schema(InstanceType::Boolean,
+                                    "Load or reload an already loaded service"));
+
+    RootSchema { meta_schema: Some("http://json-schema.org/draft-07/schema#".to_owned()),
+                 schema: SchemaObject { instance_type: Some(InstanceType::Object.into()),
+                                        object:
+                                            Some(Box::new(ObjectValidation {
+                                                properties,
+                                                required: std::iter::once("pkg_ident".to_owned())
+                                                    .collect(),
+                                                additional_properties:
+                                                    Some(Box::new(Schema::Bool(false))),
+                                                ..Default::default()
+                                            })),
+                                        ..Default::default() },
+                 definitions: Map::new() }
 }
 
 pub fn svc_loads_from_paths<T: AsRef<Path>>(paths: &[T]) -> Result<Vec<Load>> {
-    // If the only path is the default location and the directory does not exist do not report an
-    // error. This allows users to run the Supervisor without creating the directory.
-    if paths.len() == 1 {
-        let path = paths[0].as_ref();
-        if path == Path::new(DEFAULT_SVC_CONFIG_PATH) && !path.exists() {
-            return Ok(Vec::new());
+    Ok(svc_loads_from_paths_with_provenance(paths)?.into_iter()
+                                                     .map(|(svc_load, _)| svc_load)
+                                                     .collect())
+}
+
+/// Where a single resolved `SharedLoad`/`Load` field's final value came from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldSource {
+    /// Set explicitly via a flag on the invoking command line.
+    CommandLineFlag,
+    /// Overridden by a `HAB_SVC_*` environment variable.
+    EnvVar(String),
+    /// Present in the per-service config file that was scanned.
+    SvcConfigFile(PathBuf),
+    /// Left at the value from the default `svc.toml`.
+    DefaultConfigFile,
+}
+
+impl std::fmt::Display for FieldSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldSource::CommandLineFlag => write!(f, "command-line flag"),
+            FieldSource::EnvVar(var) => write!(f, "environment variable {}", var),
+            FieldSource::SvcConfigFile(path) => write!(f, "{}", path.display()),
+            FieldSource::DefaultConfigFile => write!(f, "default svc.toml"),
         }
     }
+}
+
+/// The origin of every field that went into building a single resolved `Load`, keyed by field
+/// long name (e.g. `"update-condition"`).
+pub type LoadProvenance = HashMap<&'static str, FieldSource>;
+
+/// The `SharedLoad`/`Load` fields that can be overridden by a per-service config file or a
+/// `HAB_SVC_*` environment variable, paired with their TOML key and flag long name.
+const OVERLAYABLE_FIELDS: &[(&str, &str)] = &[("channel", "channel"),
+                                               ("bldr_url", "url"),
+                                               ("group", "group"),
+                                               ("topology", "topology"),
+                                               ("strategy", "strategy"),
+                                               ("update_condition", "update-condition"),
+                                               ("bind", "bind"),
+                                               ("binding_mode", "binding-mode"),
+                                               ("health_check_interval", "health-check-interval"),
+                                               ("shutdown_timeout", "shutdown-timeout")];
+
+/// Same as [`svc_loads_from_paths`], but additionally returns, for each resolved `Load`, the
+/// [`LoadProvenance`] recording whether every field came from the per-service file, a
+/// `HAB_SVC_*` environment variable, or the default `svc.toml` value.
+pub fn svc_loads_from_paths_with_provenance<T: AsRef<Path>>(
+    paths: &[T])
+    -> Result<Vec<(Load, LoadProvenance)>> {
+    if only_path_is_missing_default(paths) {
+        return Ok(Vec::new());
+    }
     let mut svc_loads = Vec::new();
     let default_svc_load = ConfigOptLoad::from_default_config_files()?;
     for path in paths {
@@ -256,10 +493,9 @@ pub fn svc_loads_from_paths<T: AsRef<Path>>(paths: &[T]) -> Result<Vec<Load>> {
             if entry.file_type().is_file() {
                 if let Some(extension) = path.extension() {
                     if extension == "toml" {
-                        let mut svc_load = configopt::from_toml_file(path)?;
-                        // Patch the svc load with values from the default svc load
-                        default_svc_load.clone().patch_for(&mut svc_load);
-                        svc_loads.push(svc_load);
+                        let provenance = svc_load_provenance(path)?;
+                        let svc_load = parse_and_patch_svc_load(path, &default_svc_load)?;
+                        svc_loads.push((svc_load, provenance));
                     }
                 }
             }
@@ -267,3 +503,767 @@ pub fn svc_loads_from_paths<T: AsRef<Path>>(paths: &[T]) -> Result<Vec<Load>> {
     }
     Ok(svc_loads)
 }
+
+/// If the only configured path is the default location and it doesn't exist yet, there's nothing
+/// to scan: this allows users to run the Supervisor without creating the directory, rather than
+/// treating it as an error.
+fn only_path_is_missing_default<T: AsRef<Path>>(paths: &[T]) -> bool {
+    if let [path] = paths {
+        let path = path.as_ref();
+        path == Path::new(DEFAULT_SVC_CONFIG_PATH) && !path.exists()
+    } else {
+        false
+    }
+}
+
+/// Parse a single per-service config file, patch it with the default `svc.toml` values, and apply
+/// the `HAB_SVC_*` environment variable overlay.
+fn parse_and_patch_svc_load(path: &Path, default_svc_load: &ConfigOptLoad) -> Result<Load> {
+    let mut svc_load = configopt::from_toml_file(path)?;
+    // Patch the svc load with values from the default svc load
+    default_svc_load.clone().patch_for(&mut svc_load);
+    // Environment variables override both the per-service file and the default svc.toml, so
+    // operators running under systemd or containers can override individual fields without
+    // templating files. There's no command line to defer to here, so the overlay always applies.
+    apply_svc_load_env_overlay(&mut svc_load, &|_| true)?;
+    Ok(svc_load)
+}
+
+/// Determine, for a single per-service config file, which layer will supply each overlayable
+/// field: the file itself, a `HAB_SVC_*` environment variable, or (absent both) the default
+/// `svc.toml`.
+fn svc_load_provenance(path: &Path) -> Result<LoadProvenance> {
+    let mut provenance: LoadProvenance =
+        OVERLAYABLE_FIELDS.iter()
+                          .map(|(_, flag)| (*flag, FieldSource::DefaultConfigFile))
+                          .collect();
+
+    let raw_toml = toml::from_str::<toml::Value>(&std::fs::read_to_string(path)?)?;
+    if let toml::Value::Table(table) = &raw_toml {
+        for (toml_key, flag) in OVERLAYABLE_FIELDS {
+            if table.contains_key(*toml_key) {
+                provenance.insert(flag, FieldSource::SvcConfigFile(path.to_path_buf()));
+            }
+        }
+    }
+    // Environment variables are applied after the per-service file, so they take precedence over
+    // whatever the file just set
+    for (_, flag) in OVERLAYABLE_FIELDS {
+        if std::env::var(svc_load_env_var(flag)).is_ok() {
+            provenance.insert(flag, FieldSource::EnvVar(svc_load_env_var(flag)));
+        }
+    }
+    Ok(provenance)
+}
+
+/// `SharedLoad`/`Load` carry `rename_all = "screamingsnake"`, so the `clap::Arg` name `structopt`
+/// registers for a field is the SCREAMING_SNAKE_CASE form of its identifier, not the identifier
+/// itself. `OVERLAYABLE_FIELDS`' first element is the snake_case TOML/field key, so any lookup
+/// into a real `Load`/`SharedLoad` `ArgMatches` (`occurrences_of`, `value_of`, ...) must go
+/// through this conversion first. Every affected field is already underscore-separated, so this
+/// is a plain uppercase, with no word-boundary splitting to worry about.
+fn cli_arg_name(field: &str) -> String { field.to_uppercase() }
+
+/// Determine, for a `Load` parsed directly off the command line (`hab svc load --explain`),
+/// which layer supplied each overlayable field: an explicit flag on that command line, a
+/// `HAB_SVC_*` environment variable, or (absent both) the default `svc.toml`. There is no
+/// per-service config file in this path, so [`FieldSource::SvcConfigFile`] never appears here.
+pub fn svc_load_cli_provenance(matches: &structopt::clap::ArgMatches) -> LoadProvenance {
+    OVERLAYABLE_FIELDS.iter()
+                      .map(|(field, flag)| {
+                          let source = if matches.occurrences_of(&cli_arg_name(field)) > 0 {
+                              FieldSource::CommandLineFlag
+                          } else if std::env::var(svc_load_env_var(flag)).is_ok() {
+                              FieldSource::EnvVar(svc_load_env_var(flag))
+                          } else {
+                              FieldSource::DefaultConfigFile
+                          };
+                          (*flag, source)
+                      })
+                      .collect()
+}
+
+/// Apply the `HAB_SVC_*` environment variable overlay to a `Load` parsed directly off the command
+/// line (`hab svc load`), leaving alone any field the operator gave an explicit flag for, so a
+/// `--channel`/`--strategy`/etc. flag keeps outranking the environment. Pair this with
+/// [`svc_load_cli_provenance`], which reports the same precedence.
+pub fn apply_svc_load_cli_env_overlay(svc_load: &mut Load,
+                                       matches: &structopt::clap::ArgMatches)
+                                       -> Result<()> {
+    apply_svc_load_env_overlay(svc_load, &|field| matches.occurrences_of(&cli_arg_name(field)) == 0)
+}
+
+/// Print a `field = value (source)` table for a single service's resolved `Load`, showing which
+/// layer (command-line flag, environment variable, per-service config file, or default svc.toml)
+/// supplied each field.
+pub fn print_svc_load_provenance(svc_load: &Load, provenance: &LoadProvenance) {
+    println!("{}", svc_load.pkg_ident.pkg_ident);
+    println!("  channel = {} ({})",
+             svc_load.shared_load.channel,
+             provenance["channel"]);
+    println!("  url = {:?} ({})", svc_load.shared_load.bldr_url, provenance["url"]);
+    println!("  group = {} ({})", svc_load.shared_load.group, provenance["group"]);
+    println!("  topology = {:?} ({})",
+             svc_load.shared_load.topology,
+             provenance["topology"]);
+    println!("  strategy = {} ({})",
+             svc_load.shared_load.strategy,
+             provenance["strategy"]);
+    println!("  update-condition = {} ({})",
+             svc_load.shared_load.update_condition,
+             provenance["update-condition"]);
+    println!("  bind = {:?} ({})", svc_load.shared_load.bind, provenance["bind"]);
+    println!("  binding-mode = {} ({})",
+             svc_load.shared_load.binding_mode,
+             provenance["binding-mode"]);
+    println!("  health-check-interval = {} ({})",
+             svc_load.shared_load.health_check_interval,
+             provenance["health-check-interval"]);
+    println!("  shutdown-timeout = {:?} ({})",
+             svc_load.shared_load.shutdown_timeout,
+             provenance["shutdown-timeout"]);
+}
+
+/// Fixed prefix used to derive an environment variable name from a `SharedLoad`/`Load` flag's
+/// long name, e.g. `--update-condition` becomes `HAB_SVC_UPDATE_CONDITION`.
+const SVC_LOAD_ENV_PREFIX: &str = "HAB_SVC_";
+
+fn svc_load_env_var(flag_long_name: &str) -> String {
+    format!("{}{}",
+            SVC_LOAD_ENV_PREFIX,
+            flag_long_name.to_uppercase().replace('-', "_"))
+}
+
+/// Parse `flag_long_name`'s corresponding `HAB_SVC_*` environment variable, if set, through the
+/// same `FromStr` implementation the flag itself uses, so an invalid value fails with the same
+/// error the flag would give.
+fn parse_svc_load_env_var<V>(flag_long_name: &str) -> Result<Option<V>>
+    where V: std::str::FromStr,
+          V::Err: std::fmt::Display
+{
+    match std::env::var(svc_load_env_var(flag_long_name)) {
+        Ok(raw) => {
+            raw.parse()
+               .map(Some)
+               .map_err(|e| {
+                   Error::ArgParseError(format!("Invalid value for {}: {}",
+                                                 svc_load_env_var(flag_long_name),
+                                                 e))
+               })
+        }
+        Err(std::env::VarError::NotPresent) => Ok(None),
+        Err(e) => {
+            Err(Error::ArgParseError(format!("Invalid value for {}: {}",
+                                              svc_load_env_var(flag_long_name),
+                                              e)))
+        }
+    }
+}
+
+/// Overlay `HAB_SVC_*` environment variable overrides onto an already file-and-default-patched
+/// `Load`.
+///
+/// `should_overlay` is consulted once per field (keyed the same way as [`OVERLAYABLE_FIELDS`]'s
+/// TOML key) before its environment variable is read, so a caller that was parsed off a command
+/// line can skip fields the operator already gave an explicit flag for; a caller with no command
+/// line to defer to (the per-service file and bulkload paths) just always returns `true`.
+///
+/// Precedence, highest to lowest: explicit CLI flag, environment variable, per-service config
+/// file, default `svc.toml` value. This function implements the latter two layers being
+/// overridden by the environment layer, guarded by the CLI flag layer via `should_overlay`.
+fn apply_svc_load_env_overlay(svc_load: &mut Load, should_overlay: &dyn Fn(&str) -> bool) -> Result<()> {
+    if should_overlay("channel") {
+        if let Some(v) = parse_svc_load_env_var("channel")? {
+            svc_load.shared_load.channel = v;
+        }
+    }
+    if should_overlay("bldr_url") {
+        if let Some(v) = parse_svc_load_env_var("url")? {
+            svc_load.shared_load.bldr_url = Some(v);
+        }
+    }
+    if should_overlay("group") {
+        if let Some(v) = parse_svc_load_env_var("group")? {
+            svc_load.shared_load.group = v;
+        }
+    }
+    if should_overlay("topology") {
+        if let Some(v) = parse_svc_load_env_var("topology")? {
+            svc_load.shared_load.topology = Some(v);
+        }
+    }
+    if should_overlay("strategy") {
+        if let Some(v) = parse_svc_load_env_var("strategy")? {
+            svc_load.shared_load.strategy = v;
+        }
+    }
+    if should_overlay("update_condition") {
+        if let Some(v) = parse_svc_load_env_var("update-condition")? {
+            svc_load.shared_load.update_condition = v;
+        }
+    }
+    if should_overlay("bind") {
+        match std::env::var(svc_load_env_var("bind")) {
+            Ok(raw) => {
+                svc_load.shared_load.bind =
+                    raw.split(|c: char| c == ',' || c.is_whitespace())
+                       .filter(|s| !s.is_empty())
+                       .map(str::parse)
+                       .collect::<std::result::Result<Vec<ServiceBind>, _>>()
+                       .map_err(|e| {
+                           Error::ArgParseError(format!("Invalid value for {}: {}",
+                                                         svc_load_env_var("bind"),
+                                                         e))
+                       })?;
+            }
+            Err(std::env::VarError::NotPresent) => {}
+            Err(e) => {
+                return Err(Error::ArgParseError(format!("Invalid value for {}: {}",
+                                                          svc_load_env_var("bind"),
+                                                          e)));
+            }
+        }
+    }
+    if should_overlay("binding_mode") {
+        if let Some(v) = parse_svc_load_env_var("binding-mode")? {
+            svc_load.shared_load.binding_mode = v;
+        }
+    }
+    if should_overlay("health_check_interval") {
+        if let Some(v) = parse_svc_load_env_var("health-check-interval")? {
+            svc_load.shared_load.health_check_interval = v;
+        }
+    }
+    if should_overlay("shutdown_timeout") {
+        if let Some(v) = parse_svc_load_env_var("shutdown-timeout")? {
+            svc_load.shared_load.shutdown_timeout = Some(v);
+        }
+    }
+    Ok(())
+}
+
+/// A service identity for reconciliation purposes: a `Load` is considered the "same" service
+/// across scans if both its package identifier and its service group match. Two files can
+/// legitimately load the same package under different groups, so the group half can't be
+/// dropped without losing track of which running instance a change refers to.
+pub type BulkLoadKey = (PackageIdent, String);
+
+fn bulkload_key(load: &Load) -> BulkLoadKey {
+    (load.pkg_ident.pkg_ident.clone(), load.shared_load.group.clone())
+}
+
+/// The actions needed to bring a running Supervisor's bulk-loaded services in line with a fresh
+/// scan of the configured paths.
+#[derive(Default)]
+pub struct BulkLoadReconciliation {
+    /// Services that are new or whose resolved config changed since the last scan.
+    pub to_load:   Vec<Load>,
+    /// Services, keyed by `(pkg_ident, group)`, whose config file disappeared since the last
+    /// scan. The group is carried alongside the package identifier so the caller can tell which
+    /// of possibly several running instances of the same package to unload.
+    pub to_unload: Vec<BulkLoadKey>,
+}
+
+/// Diff a fresh scan of `paths` against the previously applied set of services.
+///
+/// Returns the new applied set (keyed by `(pkg_ident, group)`) alongside the reconciliation
+/// actions. A scan that fails to parse or fails `deny_unknown_fields` is surfaced as an `Err`
+/// without mutating `previous`, so callers can log the error and keep running the last-known-good
+/// configuration rather than tearing services down.
+fn reconcile_svc_loads<T: AsRef<Path>>(
+    previous: &HashMap<BulkLoadKey, Load>,
+    paths: &[T])
+    -> Result<(HashMap<BulkLoadKey, Load>, BulkLoadReconciliation)> {
+    let desired = svc_loads_from_paths(paths)?;
+    Ok(diff_svc_loads(previous, desired))
+}
+
+/// Diff an already-resolved set of desired `Load`s against the previously applied set.
+///
+/// A service is new or changed (`to_load`) if its key isn't in `previous` or its resolved `Load`
+/// no longer matches what's there; a service is gone (`to_unload`) if its key was in `previous`
+/// but isn't in `desired`. Pure and filesystem-free, split out from [`reconcile_svc_loads`] so the
+/// diffing logic can be exercised directly in tests.
+fn diff_svc_loads(previous: &HashMap<BulkLoadKey, Load>,
+                   desired: Vec<Load>)
+                   -> (HashMap<BulkLoadKey, Load>, BulkLoadReconciliation) {
+    let mut applied = HashMap::with_capacity(desired.len());
+    let mut reconciliation = BulkLoadReconciliation::default();
+    for load in desired {
+        let key = bulkload_key(&load);
+        if previous.get(&key) != Some(&load) {
+            reconciliation.to_load.push(load.clone());
+        }
+        applied.insert(key, load);
+    }
+    for key in previous.keys() {
+        if !applied.contains_key(key) {
+            reconciliation.to_unload.push(key.clone());
+        }
+    }
+    (applied, reconciliation)
+}
+
+/// The nearest ancestor of `path` that currently exists, if any (which may be `path` itself).
+///
+/// Used to watch for a not-yet-created `svc-config-paths` entry: `notify` can't register a watch
+/// on a path that doesn't exist, but it can watch the closest existing directory above it and
+/// notice when the missing path is created underneath it.
+fn nearest_existing_ancestor(path: &Path) -> Option<PathBuf> {
+    let mut current = Some(path);
+    while let Some(candidate) = current {
+        if candidate.exists() {
+            return Some(candidate.to_path_buf());
+        }
+        current = candidate.parent();
+    }
+    None
+}
+
+/// Register a recursive watch on every configured path, deferring any path that doesn't exist
+/// yet (e.g. the default `svc-config-paths` directory on a fresh install) by watching its nearest
+/// existing ancestor instead. Returns the deferred paths, each paired with the ancestor
+/// substituted for it, so the caller can promote them to their own watch once they're created.
+fn watch_svc_config_paths<T: AsRef<Path>>(watcher: &mut notify::RecommendedWatcher,
+                                           paths: &[T])
+                                           -> Result<HashMap<PathBuf, PathBuf>> {
+    let mut deferred = HashMap::new();
+    for path in paths {
+        let path = path.as_ref();
+        if path.exists() {
+            watcher.watch(path, RecursiveMode::Recursive)?;
+        } else if let Some(ancestor) = nearest_existing_ancestor(path) {
+            warn!("{} does not exist yet; watching {} until it is created",
+                  path.display(),
+                  ancestor.display());
+            watcher.watch(&ancestor, RecursiveMode::Recursive)?;
+            deferred.insert(path.to_path_buf(), ancestor);
+        } else {
+            warn!("Neither {} nor any of its ancestors exist; it will not be watched until \
+                   created",
+                  path.display());
+        }
+    }
+    Ok(deferred)
+}
+
+/// Promote any `deferred` path that has since been created to its own recursive watch, removing
+/// it from `deferred` on success. The ancestor substitute watch is left in place, since other
+/// deferred paths (or future ones) may still depend on it.
+fn promote_created_svc_config_paths(watcher: &mut notify::RecommendedWatcher,
+                                     deferred: &mut HashMap<PathBuf, PathBuf>) {
+    deferred.retain(|path, ancestor| {
+        if !path.exists() {
+            return true;
+        }
+        match watcher.watch(path, RecursiveMode::Recursive) {
+            Ok(()) => false,
+            Err(e) => {
+                warn!("{} was created but could not be watched, still falling back to {}: {}",
+                      path.display(),
+                      ancestor.display(),
+                      e);
+                true
+            }
+        }
+    });
+}
+
+/// Watch `paths` and call `on_reconcile` with the services to load/unload whenever the watched
+/// trees settle after a change, keeping a running Supervisor's bulk-loaded services in sync.
+///
+/// Rapid bursts of filesystem events (e.g. an editor's save, or a directory of files dropped in
+/// at once) are coalesced into a single reconciliation: `BULKLOAD_WATCH_DEBOUNCE` lets repeated
+/// events on the same path settle, and every event already queued by the time one is handled is
+/// drained before reconciling, so one burst yields one reconciliation rather than one per event.
+/// If a reconciliation pass fails to parse, the error is logged and the previously applied
+/// configuration is kept rather than unloading anything.
+///
+/// A configured path that doesn't exist yet (e.g. the default `svc-config-paths` directory on a
+/// fresh install) is not an error: its nearest existing ancestor is watched instead, and the path
+/// is promoted to its own watch as soon as it's created.
+pub fn watch_svc_loads_from_paths<T, F>(paths: &[T], mut on_reconcile: F) -> Result<()>
+    where T: AsRef<Path>,
+          F: FnMut(&BulkLoadReconciliation) -> Result<()>
+{
+    let (tx, rx) = channel();
+    let mut watcher = notify::watcher(tx, BULKLOAD_WATCH_DEBOUNCE)?;
+    let mut deferred = watch_svc_config_paths(&mut watcher, paths)?;
+
+    let mut applied = HashMap::new();
+    match reconcile_svc_loads(&applied, paths) {
+        Ok((new_applied, reconciliation)) => {
+            on_reconcile(&reconciliation)?;
+            applied = new_applied;
+        }
+        Err(e) => {
+            error!("Initial scan of svc-config-paths failed, starting with no services loaded: \
+                     {}",
+                    e);
+        }
+    }
+
+    loop {
+        let first_event = match rx.recv() {
+            Ok(event) => event,
+            Err(e) => {
+                error!("svc-config-paths watcher channel disconnected: {}", e);
+                return Ok(());
+            }
+        };
+        // Drain every event already queued before reconciling, so a burst of changes (e.g. many
+        // files dropped into the directory at once) triggers one reconciliation, not one per
+        // event.
+        let mut events = vec![first_event];
+        while let Ok(event) = rx.try_recv() {
+            events.push(event);
+        }
+        for event in &events {
+            if let DebouncedEvent::Error(e, path) = event {
+                warn!("Error watching svc-config-paths ({:?}): {}", path, e);
+            }
+        }
+
+        if !deferred.is_empty() {
+            promote_created_svc_config_paths(&mut watcher, &mut deferred);
+        }
+
+        match reconcile_svc_loads(&applied, paths) {
+            Ok((new_applied, reconciliation)) => {
+                on_reconcile(&reconciliation)?;
+                applied = new_applied;
+            }
+            Err(e) => {
+                error!("Failed to reload svc-config-paths, keeping previously applied \
+                        configuration: {}",
+                       e);
+            }
+        }
+    }
+}
+
+/// Every failure found while validating a bulkload config tree, keyed by the `.toml` file that
+/// failed.
+#[derive(Default, Debug)]
+pub struct BulkLoadValidationReport {
+    pub failures: std::collections::BTreeMap<PathBuf, String>,
+}
+
+impl BulkLoadValidationReport {
+    pub fn is_valid(&self) -> bool { self.failures.is_empty() }
+}
+
+impl std::fmt::Display for BulkLoadValidationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (path, error) in &self.failures {
+            writeln!(f, "{}: {}", path.display(), error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Walk all `.toml` files under `paths` and attempt the full parse-and-patch pipeline for each,
+/// collecting every failure into a single report instead of bailing on the first one. This never
+/// contacts a Supervisor or loads anything, so it is safe to run in CI to pre-flight a directory
+/// of service config files.
+pub fn validate_svc_loads_from_paths<T: AsRef<Path>>(paths: &[T]) -> BulkLoadValidationReport {
+    let mut report = BulkLoadValidationReport::default();
+    if only_path_is_missing_default(paths) {
+        return report;
+    }
+    let default_svc_load = match ConfigOptLoad::from_default_config_files() {
+        Ok(default_svc_load) => default_svc_load,
+        Err(e) => {
+            report.failures
+                  .insert(PathBuf::from(DEFAULT_SVC_TOML_PATH), e.to_string());
+            return report;
+        }
+    };
+    for path in paths {
+        for entry in WalkDir::new(path) {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    let path = e.path().map(Path::to_path_buf).unwrap_or_else(|| path.as_ref().to_path_buf());
+                    report.failures.insert(path, e.to_string());
+                    continue;
+                }
+            };
+            let file_path = entry.path();
+            if entry.file_type().is_file() {
+                if let Some(extension) = file_path.extension() {
+                    if extension == "toml" {
+                        if let Err(e) = parse_and_patch_svc_load(file_path, &default_svc_load) {
+                            report.failures.insert(file_path.to_path_buf(), e.to_string());
+                        }
+                    }
+                }
+            }
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    // The tests below set and remove real `HAB_SVC_*` process environment variables, which
+    // `parse_svc_load_env_var` and the provenance/overlay functions read; these are shared mutable
+    // state across `#[test]` threads, so serialize the tests that touch them.
+    static ENV_TEST_LOCK: Mutex<()> = Mutex::new(());
+
+    fn test_load(pkg_ident: &str, group: &str) -> Load {
+        Load { pkg_ident:              PkgIdent { pkg_ident: pkg_ident.parse()
+                                                                       .expect("valid pkg ident") },
+               force:                  false,
+               remote_sup:             RemoteSup { remote_sup: None },
+               shared_load:            SharedLoad { channel: Default::default(),
+                                                     bldr_url: None,
+                                                     group: group.to_owned(),
+                                                     topology: None,
+                                                     strategy: Default::default(),
+                                                     update_condition: Default::default(),
+                                                     bind: Vec::new(),
+                                                     binding_mode: Default::default(),
+                                                     health_check_interval: 30,
+                                                     shutdown_timeout: None,
+                                                     #[cfg(target_os = "windows")]
+                                                     password: None,
+                                                     application: Vec::new(),
+                                                     environment: Vec::new(),
+                                                     config_from: None },
+               generate_config_schema: false,
+               explain:                false }
+    }
+
+    #[test]
+    fn bulkload_key_matches_on_pkg_ident_and_group_only() {
+        let a = test_load("core/redis", "default");
+        let mut b = test_load("core/redis", "default");
+        b.force = true;
+        assert_eq!(bulkload_key(&a), bulkload_key(&b));
+
+        let c = test_load("core/redis", "other");
+        assert_ne!(bulkload_key(&a), bulkload_key(&c));
+
+        let d = test_load("core/postgresql", "default");
+        assert_ne!(bulkload_key(&a), bulkload_key(&d));
+    }
+
+    #[test]
+    fn diff_svc_loads_reports_new_file() {
+        let previous = HashMap::new();
+        let desired = vec![test_load("core/redis", "default")];
+
+        let (applied, reconciliation) = diff_svc_loads(&previous, desired.clone());
+
+        assert_eq!(reconciliation.to_load, desired);
+        assert!(reconciliation.to_unload.is_empty());
+        assert_eq!(applied.len(), 1);
+    }
+
+    #[test]
+    fn diff_svc_loads_reports_unchanged_file_as_a_no_op() {
+        let load = test_load("core/redis", "default");
+        let mut previous = HashMap::new();
+        previous.insert(bulkload_key(&load), load.clone());
+
+        let (applied, reconciliation) = diff_svc_loads(&previous, vec![load.clone()]);
+
+        assert!(reconciliation.to_load.is_empty());
+        assert!(reconciliation.to_unload.is_empty());
+        assert_eq!(applied.get(&bulkload_key(&load)), Some(&load));
+    }
+
+    #[test]
+    fn diff_svc_loads_reports_changed_file() {
+        let old_load = test_load("core/redis", "default");
+        let mut previous = HashMap::new();
+        previous.insert(bulkload_key(&old_load), old_load);
+
+        let mut new_load = test_load("core/redis", "default");
+        new_load.shared_load.health_check_interval = 60;
+
+        let (_, reconciliation) = diff_svc_loads(&previous, vec![new_load.clone()]);
+
+        assert_eq!(reconciliation.to_load, vec![new_load]);
+        assert!(reconciliation.to_unload.is_empty());
+    }
+
+    #[test]
+    fn diff_svc_loads_reports_deleted_file() {
+        let load = test_load("core/redis", "default");
+        let mut previous = HashMap::new();
+        previous.insert(bulkload_key(&load), load.clone());
+
+        let (applied, reconciliation) = diff_svc_loads(&previous, Vec::new());
+
+        assert!(reconciliation.to_load.is_empty());
+        assert_eq!(reconciliation.to_unload, vec![bulkload_key(&load)]);
+        assert!(applied.is_empty());
+    }
+
+    #[test]
+    fn diff_svc_loads_unloads_only_the_deleted_group_of_a_shared_package() {
+        let primary = test_load("core/redis", "primary");
+        let secondary = test_load("core/redis", "secondary");
+        let mut previous = HashMap::new();
+        previous.insert(bulkload_key(&primary), primary.clone());
+        previous.insert(bulkload_key(&secondary), secondary.clone());
+
+        // Only `secondary`'s file is still around; `primary`'s config file was deleted.
+        let (applied, reconciliation) = diff_svc_loads(&previous, vec![secondary.clone()]);
+
+        assert_eq!(reconciliation.to_unload, vec![bulkload_key(&primary)]);
+        assert!(reconciliation.to_load.is_empty());
+        assert!(applied.contains_key(&bulkload_key(&secondary)));
+        assert!(!applied.contains_key(&bulkload_key(&primary)));
+    }
+
+    #[test]
+    fn bulk_load_validation_report_is_valid_iff_empty() {
+        let mut report = BulkLoadValidationReport::default();
+        assert!(report.is_valid());
+
+        report.failures
+              .insert(PathBuf::from("bad.toml"), "missing field `pkg_ident`".to_owned());
+        assert!(!report.is_valid());
+        assert_eq!(report.to_string(), "bad.toml: missing field `pkg_ident`\n");
+    }
+
+    #[test]
+    fn svc_load_config_schema_requires_pkg_ident_and_forbids_unknown_fields() {
+        let schema: serde_json::Value =
+            serde_json::from_str(&svc_load_config_schema().expect("schema renders"))
+                .expect("valid json");
+
+        assert_eq!(schema["required"], serde_json::json!(["pkg_ident"]));
+        assert_eq!(schema["additionalProperties"], serde_json::json!(false));
+    }
+
+    #[test]
+    fn svc_load_config_schema_allows_config_from() {
+        let schema: serde_json::Value =
+            serde_json::from_str(&svc_load_config_schema().expect("schema renders"))
+                .expect("valid json");
+
+        assert_eq!(schema["properties"]["config_from"]["type"], serde_json::json!("string"));
+    }
+
+    #[test]
+    fn svc_load_config_schema_surfaces_each_field_default() {
+        let schema: serde_json::Value =
+            serde_json::from_str(&svc_load_config_schema().expect("schema renders"))
+                .expect("valid json");
+
+        assert_eq!(schema["properties"]["channel"]["default"],
+                   serde_json::json!(CHANNEL_IDENT_DEFAULT.as_str()));
+        assert_eq!(schema["properties"]["group"]["default"], serde_json::json!("default"));
+        assert_eq!(schema["properties"]["strategy"]["default"], serde_json::json!("none"));
+        assert_eq!(schema["properties"]["update_condition"]["default"],
+                   serde_json::json!("latest"));
+        assert_eq!(schema["properties"]["binding_mode"]["default"], serde_json::json!("strict"));
+        assert_eq!(schema["properties"]["health_check_interval"]["default"],
+                   serde_json::json!(30));
+    }
+
+    #[test]
+    fn bulkload_mode_flags_are_mutually_exclusive() {
+        let result = BulkLoad::clap().get_matches_from_safe(vec!["bulkload", "--watch",
+                                                                  "--validate"]);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn svc_load_cli_provenance_reports_command_line_flag_for_a_given_flag() {
+        let matches = SharedLoad::clap().get_matches_from(vec!["test", "--channel", "stable"]);
+
+        let provenance = svc_load_cli_provenance(&matches);
+
+        assert_eq!(provenance["channel"], FieldSource::CommandLineFlag);
+    }
+
+    #[test]
+    fn svc_load_cli_provenance_falls_through_to_default_when_no_flag_was_given() {
+        let _guard = ENV_TEST_LOCK.lock().unwrap();
+        std::env::remove_var("HAB_SVC_CHANNEL");
+
+        let matches = SharedLoad::clap().get_matches_from(vec!["test"]);
+
+        let provenance = svc_load_cli_provenance(&matches);
+
+        assert_eq!(provenance["channel"], FieldSource::DefaultConfigFile);
+    }
+
+    #[test]
+    fn svc_load_env_var_upcases_and_prefixes_the_flag_name() {
+        assert_eq!(svc_load_env_var("update-condition"), "HAB_SVC_UPDATE_CONDITION");
+        assert_eq!(svc_load_env_var("channel"), "HAB_SVC_CHANNEL");
+    }
+
+    #[test]
+    fn parse_svc_load_env_var_is_none_when_not_present() {
+        let _guard = ENV_TEST_LOCK.lock().unwrap();
+        std::env::remove_var("HAB_SVC_HEALTH_CHECK_INTERVAL");
+
+        let parsed = parse_svc_load_env_var::<u64>("health-check-interval").unwrap();
+
+        assert_eq!(parsed, None);
+    }
+
+    #[test]
+    fn parse_svc_load_env_var_parses_a_valid_value() {
+        let _guard = ENV_TEST_LOCK.lock().unwrap();
+        std::env::set_var("HAB_SVC_HEALTH_CHECK_INTERVAL", "60");
+
+        let parsed = parse_svc_load_env_var::<u64>("health-check-interval").unwrap();
+
+        std::env::remove_var("HAB_SVC_HEALTH_CHECK_INTERVAL");
+        assert_eq!(parsed, Some(60));
+    }
+
+    #[test]
+    fn parse_svc_load_env_var_surfaces_an_invalid_value_as_an_error() {
+        let _guard = ENV_TEST_LOCK.lock().unwrap();
+        std::env::set_var("HAB_SVC_HEALTH_CHECK_INTERVAL", "not-a-number");
+
+        let result = parse_svc_load_env_var::<u64>("health-check-interval");
+
+        std::env::remove_var("HAB_SVC_HEALTH_CHECK_INTERVAL");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn apply_svc_load_cli_env_overlay_keeps_an_explicit_flag_over_a_conflicting_env_var() {
+        let _guard = ENV_TEST_LOCK.lock().unwrap();
+        std::env::set_var("HAB_SVC_CHANNEL", "bogus");
+
+        let matches = SharedLoad::clap().get_matches_from(vec!["test", "--channel", "stable"]);
+        let mut svc_load = test_load("core/redis", "default");
+        svc_load.shared_load.channel = "stable".parse().expect("valid channel");
+
+        let result = apply_svc_load_cli_env_overlay(&mut svc_load, &matches);
+
+        std::env::remove_var("HAB_SVC_CHANNEL");
+        result.unwrap();
+        assert_eq!(svc_load.shared_load.channel.to_string(), "stable");
+    }
+
+    #[test]
+    fn apply_svc_load_cli_env_overlay_applies_env_var_when_no_flag_was_given() {
+        let _guard = ENV_TEST_LOCK.lock().unwrap();
+        std::env::set_var("HAB_SVC_CHANNEL", "staging");
+
+        let matches = SharedLoad::clap().get_matches_from(vec!["test"]);
+        let mut svc_load = test_load("core/redis", "default");
+
+        let result = apply_svc_load_cli_env_overlay(&mut svc_load, &matches);
+
+        std::env::remove_var("HAB_SVC_CHANNEL");
+        result.unwrap();
+        assert_eq!(svc_load.shared_load.channel.to_string(), "staging");
+    }
+}